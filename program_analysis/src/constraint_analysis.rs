@@ -1,4 +1,5 @@
 use log::{debug, trace};
+use std::cmp::Ordering::*;
 use std::collections::{HashMap, HashSet};
 
 use program_structure::cfg::Cfg;
@@ -7,14 +8,172 @@ use program_structure::intermediate_representation::AssignOp;
 use program_structure::ir::variable_meta::VariableUse;
 use program_structure::ir::{Statement, VariableName};
 
+/// Identifies a connected component of a (symmetric) variable relation. Two
+/// variables share a `ComponentId` if and only if they are connected by a chain
+/// of steps in the same relation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ComponentId(usize);
+
+/// The kind of statement that gave rise to a step in the variable relation.
+///
+/// `ConstraintEq` (`===`) and `ConstrainAssign` (`<==`) both impose an actual
+/// constraint on the witness and feed the constraint relation. `WitnessAssign`
+/// (`<--`) only _computes_ a value for a signal without constraining it, and is
+/// tracked in the separate computation relation. A signal that is computed by a
+/// witness expression but not constrained by a matching `===`/`<==` is exactly
+/// the dangerous `<--`-without-`===` pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintEdge {
+    ConstraintEq,
+    ConstrainAssign,
+    WitnessAssign,
+}
+
+/// A disjoint-set (union-find) forest over the variables of a relation, used to
+/// maintain the connected components incrementally as steps are added. Uses
+/// union-by-rank and path halving, which gives effectively constant-time
+/// `union` and `find`.
+#[derive(Clone, Default)]
+struct DisjointSet {
+    index: HashMap<VariableUse, usize>,
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+}
+
+impl DisjointSet {
+    /// Returns the node id of `var`, inserting it as its own singleton set if
+    /// it has not been seen before.
+    fn singleton(&mut self, var: &VariableUse) -> usize {
+        if let Some(&id) = self.index.get(var) {
+            return id;
+        }
+        let id = self.parent.len();
+        self.index.insert(var.clone(), id);
+        self.parent.push(id);
+        self.rank.push(0);
+        id
+    }
+
+    /// Returns the representative of the set containing `id`, compressing the
+    /// path to the root along the way.
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parent[id] != id {
+            // Path halving: point each node at its grandparent.
+            self.parent[id] = self.parent[self.parent[id]];
+            id = self.parent[id];
+        }
+        id
+    }
+
+    /// Merges the sets containing `left` and `right`.
+    fn union(&mut self, left: &VariableUse, right: &VariableUse) {
+        let left = self.singleton(left);
+        let right = self.singleton(right);
+        let (left, right) = (self.find(left), self.find(right));
+        if left == right {
+            return;
+        }
+        match self.rank[left].cmp(&self.rank[right]) {
+            Less => self.parent[left] = right,
+            Greater => self.parent[right] = left,
+            Equal => {
+                self.parent[right] = left;
+                self.rank[left] += 1;
+            }
+        }
+    }
+}
+
+/// A symmetric relation over variable accesses, together with its connected
+/// components. The components are precomputed once (using the union-find above)
+/// when the relation is finalized, which turns closure queries into
+/// near-constant-time component lookups.
+#[derive(Clone, Default)]
+struct Relation {
+    edges: HashMap<VariableUse, HashSet<VariableUse>>,
+    disjoint_set: DisjointSet,
+    component_of: HashMap<VariableUse, ComponentId>,
+    components: HashMap<ComponentId, HashSet<VariableUse>>,
+}
+
+impl Relation {
+    /// Add a step from source to sink, merging their components.
+    fn add_step(&mut self, source: &VariableUse, sink: &VariableUse) {
+        let sinks = self.edges.entry(source.clone()).or_default();
+        sinks.insert(sink.clone());
+        self.disjoint_set.union(source, sink);
+    }
+
+    /// Collapse the union-find forest into the final component assignment. Must
+    /// be called once, after all steps have been added.
+    fn finalize(&mut self) {
+        let variables = self.disjoint_set.index.keys().cloned().collect::<Vec<_>>();
+        for variable in variables {
+            let id = self.disjoint_set.index[&variable];
+            let component = ComponentId(self.disjoint_set.find(id));
+            self.component_of.insert(variable.clone(), component);
+            self.components.entry(component).or_default().insert(variable);
+        }
+    }
+
+    /// Returns the variables directly related to `source`.
+    fn single_step(&self, source: &VariableUse) -> HashSet<VariableUse> {
+        self.edges.get(source).cloned().unwrap_or_default()
+    }
+
+    /// Returns the variables related to `source` in one or more steps, i.e. the
+    /// other members of `source`'s connected component.
+    fn multi_step(&self, source: &VariableUse) -> HashSet<VariableUse> {
+        match self.component_of(source) {
+            Some(component) => {
+                self.components[&component].iter().filter(|&sink| sink != source).cloned().collect()
+            }
+            None => HashSet::new(),
+        }
+    }
+
+    fn component_of(&self, source: &VariableUse) -> Option<ComponentId> {
+        self.component_of.get(source).copied()
+    }
+
+    fn components(&self) -> impl Iterator<Item = &HashSet<VariableUse>> {
+        self.components.values()
+    }
+
+    fn variables(&self) -> HashSet<VariableUse> {
+        self.edges.keys().cloned().collect()
+    }
+}
+
 /// This analysis computes the transitive closure of the constraint relation.
 /// (Note that the resulting relation will be symmetric, but not reflexive in
 /// general.)
+///
+/// The relation is keyed on the full variable access path (`VariableUse`)
+/// rather than the bare `VariableName`, which means that each distinct signal
+/// element is tracked as its own node. This matters for per-element
+/// initializations of component and signal arrays. For example, in
+///
+/// ```circom
+///   component c[2];
+///   ...
+///   c[0].in[0] <== 0;
+///   c[1].in[1] <== 1;
+/// ```
+///
+/// the two assignments touch different nodes (`c[0].in[0]` and `c[1].in[1]`)
+/// and are no longer conflated into a single `c` entry.
+///
+/// Alongside the constraint relation (built from `===` and `<==`), the analysis
+/// tracks a separate _computation_ relation built from `<--` witness
+/// assignments. This lets downstream passes reason about signals that are
+/// computed by a witness expression but not actually constrained by it.
 #[derive(Clone, Default)]
 pub struct ConstraintAnalysis {
-    constraint_map: HashMap<VariableName, HashSet<VariableName>>,
-    declarations: HashMap<VariableName, VariableUse>,
-    definitions: HashMap<VariableName, VariableUse>,
+    constraints: Relation,
+    computations: Relation,
+    declarations: HashMap<VariableUse, VariableUse>,
+    definitions: HashMap<VariableUse, VariableUse>,
 }
 
 impl ConstraintAnalysis {
@@ -24,22 +183,11 @@ impl ConstraintAnalysis {
 
     /// Add the variable use corresponding to the definition of the variable.
     fn add_definition(&mut self, var: &VariableUse) {
-        // TODO: Since we don't version components and signals, we may end up
-        // overwriting component initializations here. For example, in the
-        // following case the component initialization will be clobbered.
-        //
-        //   component c[2];
-        //   ...
-        //   c[0].in[0] <== 0;
-        //   c[1].in[1] <== 1;
-        //
-        // The constraint map should probably track VariableAccesses rather
-        // than VariableNames.
-        self.definitions.insert(var.name().clone(), var.clone());
+        self.definitions.insert(var.clone(), var.clone());
     }
 
     /// Get the variable use corresponding to the definition of the variable.
-    pub fn get_definition(&self, var: &VariableName) -> Option<VariableUse> {
+    pub fn get_definition(&self, var: &VariableUse) -> Option<VariableUse> {
         self.definitions.get(var).cloned()
     }
 
@@ -49,11 +197,11 @@ impl ConstraintAnalysis {
 
     /// Add the variable use corresponding to the declaration of the variable.
     fn add_declaration(&mut self, var: &VariableUse) {
-        self.declarations.insert(var.name().clone(), var.clone());
+        self.declarations.insert(var.clone(), var.clone());
     }
 
     /// Get the variable use corresponding to the declaration of the variable.
-    pub fn get_declaration(&self, var: &VariableName) -> Option<VariableUse> {
+    pub fn get_declaration(&self, var: &VariableUse) -> Option<VariableUse> {
         self.declarations.get(var).cloned()
     }
 
@@ -61,37 +209,112 @@ impl ConstraintAnalysis {
         self.declarations.values()
     }
 
-    /// Add a constraint from source to sink.
-    fn add_constraint_step(&mut self, source: &VariableName, sink: &VariableName) {
-        let sinks = self.constraint_map.entry(source.clone()).or_default();
-        sinks.insert(sink.clone());
+    /// Add a step from source to sink, recording the kind of statement it came
+    /// from. `===`/`<==` steps feed the constraint relation; `<--` steps feed
+    /// the computation relation.
+    fn add_constraint_step(&mut self, source: &VariableUse, sink: &VariableUse, edge: ConstraintEdge) {
+        use ConstraintEdge::*;
+        match edge {
+            ConstraintEq | ConstrainAssign => self.constraints.add_step(source, sink),
+            WitnessAssign => self.computations.add_step(source, sink),
+        }
+    }
+
+    /// Collapse both relations into their final component assignments.
+    fn finalize(&mut self) {
+        self.constraints.finalize();
+        self.computations.finalize();
+    }
+
+    /// Returns the constraint component containing `source`, if it occurs in
+    /// the constraint relation.
+    pub fn component_of(&self, source: &VariableUse) -> Option<ComponentId> {
+        self.constraints.component_of(source)
+    }
+
+    /// Returns an iterator over the connected components of the constraint
+    /// relation.
+    pub fn components(&self) -> impl Iterator<Item = &HashSet<VariableUse>> {
+        self.constraints.components()
     }
 
     /// Returns variables constrained in a single step by `source`.
-    pub fn single_step_constraint(&self, source: &VariableName) -> HashSet<VariableName> {
-        self.constraint_map.get(source).cloned().unwrap_or_default()
+    pub fn single_step_constraint(&self, source: &VariableUse) -> HashSet<VariableUse> {
+        self.constraints.single_step(source)
     }
 
-    /// Returns variables constrained in one or more steps by `source`.
-    pub fn multi_step_constraint(&self, source: &VariableName) -> HashSet<VariableName> {
-        let mut result = HashSet::new();
-        let mut update = self.single_step_constraint(source);
-        while !update.is_subset(&result) {
-            result.extend(update.iter().cloned());
-            update = update.iter().flat_map(|source| self.single_step_constraint(source)).collect();
-        }
-        result
+    /// Returns variables constrained in one or more steps by `source`. Since
+    /// the relation is symmetric, this is exactly the other members of
+    /// `source`'s connected component.
+    pub fn multi_step_constraint(&self, source: &VariableUse) -> HashSet<VariableUse> {
+        self.constraints.multi_step(source)
+    }
+
+    /// Returns variables computed in a single step by `source` via a `<--`
+    /// witness assignment.
+    pub fn single_step_computation(&self, source: &VariableUse) -> HashSet<VariableUse> {
+        self.computations.single_step(source)
+    }
+
+    /// Returns variables computed in one or more steps by `source` via `<--`
+    /// witness assignments.
+    pub fn multi_step_computation(&self, source: &VariableUse) -> HashSet<VariableUse> {
+        self.computations.multi_step(source)
     }
 
     /// Returns true if the source constrains any of the sinks.
-    pub fn constrains_any(&self, source: &VariableName, sinks: &HashSet<VariableName>) -> bool {
+    pub fn constrains_any(&self, source: &VariableUse, sinks: &HashSet<VariableUse>) -> bool {
         self.multi_step_constraint(source).iter().any(|sink| sinks.contains(sink))
     }
 
     /// Returns the set of variables occurring in a constraint together with at
     /// least one other variable.
-    pub fn constrained_variables(&self) -> HashSet<VariableName> {
-        self.constraint_map.keys().cloned().collect::<HashSet<_>>()
+    pub fn constrained_variables(&self) -> HashSet<VariableUse> {
+        self.constraints.variables()
+    }
+
+    // The following methods are name-keyed adapters over the `VariableUse`-keyed
+    // API above, kept for callers that only have a bare `VariableName` and do
+    // not need per-access-path precision. A name may correspond to several
+    // accesses (distinct array or component elements), so the constraint
+    // adapters aggregate over every access sharing the name, while the
+    // definition and declaration lookups return a single matching access.
+
+    /// Get the variable use corresponding to the definition of a bare name. If
+    /// several accesses share the name, which one is returned is not specified.
+    pub fn get_definition_by_name(&self, name: &VariableName) -> Option<VariableUse> {
+        self.definitions.values().find(|var| var.name() == name).cloned()
+    }
+
+    /// Get the variable use corresponding to the declaration of a bare name. If
+    /// several accesses share the name, which one is returned is not specified.
+    pub fn get_declaration_by_name(&self, name: &VariableName) -> Option<VariableUse> {
+        self.declarations.values().find(|var| var.name() == name).cloned()
+    }
+
+    /// Returns variables constrained in a single step by any access of `name`.
+    pub fn single_step_constraint_by_name(&self, name: &VariableName) -> HashSet<VariableUse> {
+        self.accesses_of(name).iter().flat_map(|var| self.single_step_constraint(var)).collect()
+    }
+
+    /// Returns variables constrained in one or more steps by any access of
+    /// `name`.
+    pub fn multi_step_constraint_by_name(&self, name: &VariableName) -> HashSet<VariableUse> {
+        self.accesses_of(name).iter().flat_map(|var| self.multi_step_constraint(var)).collect()
+    }
+
+    /// Returns true if any access of `name` constrains any of the sinks.
+    pub fn constrains_any_by_name(
+        &self,
+        name: &VariableName,
+        sinks: &HashSet<VariableUse>,
+    ) -> bool {
+        self.multi_step_constraint_by_name(name).iter().any(|sink| sinks.contains(sink))
+    }
+
+    /// Returns every constrained access sharing the given name.
+    fn accesses_of(&self, name: &VariableName) -> HashSet<VariableUse> {
+        self.constrained_variables().into_iter().filter(|var| var.name() == name).collect()
     }
 }
 
@@ -100,6 +323,7 @@ pub fn run_constraint_analysis(cfg: &Cfg) -> ConstraintAnalysis {
     let mut result = ConstraintAnalysis::new();
 
     use AssignOp::*;
+    use ConstraintEdge::*;
     use Statement::*;
     for basic_block in cfg.iter() {
         for stmt in basic_block.iter() {
@@ -115,27 +339,34 @@ pub fn run_constraint_analysis(cfg: &Cfg) -> ConstraintAnalysis {
                         result.add_declaration(&VariableUse::new(meta, sink, &Vec::new()));
                     }
                 }
-                ConstraintEquality { .. } | Substitution { op: AssignConstraintSignal, .. } => {
-                    for source in stmt.variables_used() {
-                        for sink in stmt.variables_used() {
-                            if source.name() != sink.name() {
-                                trace!(
-                                    "adding constraint step with source `{:?}` and sink `{:?}`",
-                                    source.name(),
-                                    sink.name()
-                                );
-                                result.add_constraint_step(source.name(), sink.name());
-                            }
-                        }
-                    }
+                ConstraintEquality { .. } => add_steps(&mut result, stmt, ConstraintEq),
+                Substitution { op: AssignConstraintSignal, .. } => {
+                    add_steps(&mut result, stmt, ConstrainAssign)
+                }
+                Substitution { op: AssignSignal, .. } => {
+                    add_steps(&mut result, stmt, WitnessAssign)
                 }
                 _ => {}
             }
         }
     }
+    result.finalize();
     result
 }
 
+/// Add a step between each pair of distinct variables used by `stmt`, tagged
+/// with the given edge provenance.
+fn add_steps(result: &mut ConstraintAnalysis, stmt: &Statement, edge: ConstraintEdge) {
+    for source in stmt.variables_used() {
+        for sink in stmt.variables_used() {
+            if source != sink {
+                trace!("adding {edge:?} step with source `{source}` and sink `{sink}`");
+                result.add_constraint_step(source, sink, edge);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use parser::parse_definition;
@@ -158,11 +389,7 @@ mod tests {
 
             }
         "#;
-        let sources = [
-            VariableName::from_name("in"),
-            VariableName::from_name("out"),
-            VariableName::from_name("tmp"),
-        ];
+        let sources = ["in", "out", "tmp"];
         let sinks = [2, 1, 1];
         validate_constraints(src, &sources, &sinks);
 
@@ -177,17 +404,124 @@ mod tests {
 
             }
         "#;
-        let sources = [
-            VariableName::from_name("in"),
-            VariableName::from_name("out"),
-            VariableName::from_name("tmp"),
-        ];
+        let sources = ["in", "out", "tmp"];
         let sinks = [2, 1, 1];
         validate_constraints(src, &sources, &sinks);
     }
 
-    fn validate_constraints(src: &str, sources: &[VariableName], sinks: &[usize]) {
-        // Build CFG.
+    #[test]
+    fn test_array_accesses_are_distinct_nodes() {
+        // Each array element must be tracked as its own node. Here `out[0]` is
+        // constrained by `in[0]` and `out[1]` by `in[1]`; the two elements do
+        // not share constraint steps.
+        let src = r#"
+            template T() {
+                signal input in[2];
+                signal output out[2];
+
+                out[0] <== in[0];
+                out[1] <== in[1];
+            }
+        "#;
+        let sources = ["out[0]", "out[1]", "in[0]", "in[1]"];
+        let sinks = [1, 1, 1, 1];
+        validate_constraints(src, &sources, &sinks);
+    }
+
+    #[test]
+    fn test_component_accesses_are_distinct_nodes() {
+        // Per-element component initializations must not clobber each other.
+        let src = r#"
+            template Inner() {
+                signal input in;
+                signal output out;
+                out <== in;
+            }
+            template T() {
+                signal input a;
+                signal input b;
+                component c[2];
+
+                c[0] = Inner();
+                c[1] = Inner();
+                c[0].in <== a;
+                c[1].in <== b;
+            }
+        "#;
+        let sources = ["c[0].in", "c[1].in"];
+        let sinks = [1, 1];
+        validate_constraints(src, &sources, &sinks);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let src = r#"
+            template T() {
+                signal input in;
+                signal output out;
+                signal tmp;
+
+                tmp <== 2 * in;
+                out <== tmp + in;
+            }
+        "#;
+        let cfg = build_cfg(src);
+        let constraint_analysis = run_constraint_analysis(&cfg);
+
+        // `in`, `tmp` and `out` are all transitively constrained together, so
+        // they form a single component.
+        let in_var = find_variable(&constraint_analysis, "in");
+        let out_var = find_variable(&constraint_analysis, "out");
+        let tmp_var = find_variable(&constraint_analysis, "tmp");
+        assert_eq!(constraint_analysis.components().count(), 1);
+        assert_eq!(
+            constraint_analysis.component_of(&in_var),
+            constraint_analysis.component_of(&out_var)
+        );
+        assert_eq!(
+            constraint_analysis.component_of(&in_var),
+            constraint_analysis.component_of(&tmp_var)
+        );
+        assert_eq!(constraint_analysis.multi_step_constraint(&in_var).len(), 2);
+    }
+
+    #[test]
+    fn test_witness_assignment_is_not_a_constraint() {
+        // `out <-- in * in;` computes `out` but does not constrain it, so the
+        // step belongs to the computation relation, not the constraint one.
+        let src = r#"
+            template T() {
+                signal input in;
+                signal output out;
+
+                out <-- in * in;
+            }
+        "#;
+        let cfg = build_cfg(src);
+        let constraint_analysis = run_constraint_analysis(&cfg);
+
+        assert!(constraint_analysis.constrained_variables().is_empty());
+        let out_var = constraint_analysis
+            .definitions()
+            .find(|var| var.to_string().starts_with("out"))
+            .cloned()
+            .unwrap();
+        assert_eq!(constraint_analysis.single_step_computation(&out_var).len(), 1);
+        assert!(constraint_analysis.single_step_constraint(&out_var).is_empty());
+    }
+
+    fn validate_constraints(src: &str, sources: &[&str], sinks: &[usize]) {
+        let cfg = build_cfg(src);
+
+        // Run constraint analysis.
+        let constraint_analysis = run_constraint_analysis(&cfg);
+        for (source, sinks) in sources.iter().zip(sinks) {
+            let source = find_variable(&constraint_analysis, source);
+            assert_eq!(constraint_analysis.single_step_constraint(&source).len(), *sinks)
+        }
+    }
+
+    fn build_cfg(src: &str) -> Cfg {
         let mut reports = ReportCollection::new();
         let cfg = parse_definition(src)
             .unwrap()
@@ -196,11 +530,16 @@ mod tests {
             .into_ssa()
             .unwrap();
         assert!(reports.is_empty());
+        cfg
+    }
 
-        // Run constraint analysis.
-        let constraint_analysis = run_constraint_analysis(&cfg);
-        for (source, sinks) in sources.iter().zip(sinks) {
-            assert_eq!(constraint_analysis.single_step_constraint(source).len(), *sinks)
-        }
+    /// Look up a constrained variable by its rendered access path, ignoring any
+    /// SSA version suffix added by `into_ssa`.
+    fn find_variable(analysis: &ConstraintAnalysis, access: &str) -> VariableUse {
+        analysis
+            .constrained_variables()
+            .into_iter()
+            .find(|var| var.to_string().starts_with(access))
+            .unwrap_or_else(|| panic!("no constrained variable matching `{access}`"))
     }
 }
@@ -0,0 +1,15 @@
+pub mod constraint_analysis;
+pub mod under_constrained_signals;
+
+use program_structure::cfg::Cfg;
+use program_structure::report::ReportCollection;
+
+use under_constrained_signals::run_under_constrained_signals;
+
+/// Runs the circuit analysis passes in this crate over `cfg` and returns the
+/// reports they produce.
+pub fn run_analysis(cfg: &Cfg) -> ReportCollection {
+    let mut reports = ReportCollection::new();
+    reports.extend(run_under_constrained_signals(cfg));
+    reports
+}
@@ -0,0 +1,264 @@
+use log::debug;
+use std::collections::{HashMap, HashSet};
+
+use program_structure::cfg::Cfg;
+use program_structure::intermediate_representation::{AssignOp, SignalType, VariableType};
+use program_structure::ir::variable_meta::VariableUse;
+use program_structure::ir::{Statement, VariableName};
+use program_structure::report::{Report, ReportCollection};
+use program_structure::report_code::ReportCode;
+
+use crate::constraint_analysis::run_constraint_analysis;
+
+/// An output or intermediate signal which is never transitively tied to an
+/// input signal by a constraint. Such a signal can typically be assigned a
+/// free value by a malicious prover, which is a classic source of unsound
+/// circuits.
+pub struct UnderConstrainedSignalWarning {
+    signal: VariableUse,
+    signal_type: SignalType,
+}
+
+impl UnderConstrainedSignalWarning {
+    pub fn into_report(self) -> Report {
+        let kind = signal_type_string(&self.signal_type);
+        let mut report = Report::warning(
+            format!(
+                "The {kind} signal `{}` is not constrained by any input signal.",
+                self.signal.name()
+            ),
+            ReportCode::UnderConstrainedSignal,
+        );
+        if let Some(file_id) = self.signal.meta().file_id {
+            report.add_primary(
+                self.signal.meta().file_location(),
+                file_id,
+                "This signal does not depend on any input signal.".to_string(),
+            );
+        }
+        report
+    }
+}
+
+/// A signal which is assigned using the witness-assignment operator `<--` but
+/// never occurs in a constraint. The assigned value is not checked by the
+/// circuit and can be replaced by an arbitrary field element.
+pub struct UnconstrainedSignalWarning {
+    signal: VariableUse,
+    signal_type: SignalType,
+}
+
+impl UnconstrainedSignalWarning {
+    pub fn into_report(self) -> Report {
+        let kind = signal_type_string(&self.signal_type);
+        let mut report = Report::warning(
+            format!(
+                "The {kind} signal `{}` is assigned but never constrained.",
+                self.signal.name()
+            ),
+            ReportCode::UnconstrainedSignal,
+        );
+        if let Some(file_id) = self.signal.meta().file_id {
+            report.add_primary(
+                self.signal.meta().file_location(),
+                file_id,
+                "This signal is assigned a value that is not constrained.".to_string(),
+            );
+        }
+        report
+    }
+}
+
+fn signal_type_string(signal_type: &SignalType) -> &'static str {
+    use SignalType::*;
+    match signal_type {
+        Input => "input",
+        Output => "output",
+        Intermediate => "intermediate",
+    }
+}
+
+/// Flags output and intermediate signals that are not soundly constrained:
+/// either they are never transitively connected to an input signal, or they are
+/// assigned a value that never enters a constraint. Only signal accesses that
+/// occur in a constraint or a `<--` assignment are examined; a declared signal
+/// that occurs in neither is not reported.
+pub fn run_under_constrained_signals(cfg: &Cfg) -> ReportCollection {
+    debug!("running under-constrained signal analysis pass");
+    let constraints = run_constraint_analysis(cfg);
+
+    // Record the declared type of each signal, and collect the per-element
+    // accesses that occur in an actual constraint (`===`/`<==`) and the
+    // per-element accesses that are written by a witness assignment (`<--`). We
+    // key everything on the full `VariableUse` access path so that distinct
+    // array and component elements (e.g. `out[0]` vs `out[1]`) are vetted
+    // independently.
+    let mut signal_types = HashMap::new();
+    let mut constrained_accesses = HashSet::new();
+    let mut witness_writes = HashSet::new();
+    for basic_block in cfg.iter() {
+        for stmt in basic_block.iter() {
+            match stmt {
+                Statement::Declaration { names, var_type, .. } => {
+                    if let VariableType::Signal(signal_type, _) = var_type {
+                        for name in names {
+                            signal_types.insert(name.clone(), *signal_type);
+                        }
+                    }
+                }
+                Statement::ConstraintEquality { .. }
+                | Statement::Substitution { op: AssignOp::AssignConstraintSignal, .. } => {
+                    constrained_accesses.extend(stmt.variables_used().cloned());
+                }
+                Statement::Substitution { op: AssignOp::AssignSignal, .. } => {
+                    witness_writes.extend(stmt.variables_written().cloned());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // The constraint components that contain at least one input signal access.
+    // Any derived access that lands in one of these components is transitively
+    // tied to an input.
+    let input_components = constrained_accesses
+        .iter()
+        .filter(|access| is_input(&signal_types, access))
+        .filter_map(|access| constraints.component_of(access))
+        .collect::<HashSet<_>>();
+
+    let mut reports = ReportCollection::new();
+
+    // An output or intermediate signal access that occurs in a constraint but
+    // whose component reaches no input signal is never tied to an input.
+    for access in &constrained_accesses {
+        if let Some(signal_type) = derived_signal_type(&signal_types, access) {
+            let tied_to_input = constraints
+                .component_of(access)
+                .map_or(false, |component| input_components.contains(&component));
+            if !tied_to_input {
+                reports.push(
+                    UnderConstrainedSignalWarning { signal: access.clone(), signal_type }
+                        .into_report(),
+                );
+            }
+        }
+    }
+
+    // An output or intermediate signal access that is written by a `<--`
+    // witness assignment but never occurs in any constraint is assigned a value
+    // that the circuit does not check.
+    for access in &witness_writes {
+        if constrained_accesses.contains(access) {
+            continue;
+        }
+        if let Some(signal_type) = derived_signal_type(&signal_types, access) {
+            reports.push(
+                UnconstrainedSignalWarning { signal: access.clone(), signal_type }.into_report(),
+            );
+        }
+    }
+
+    reports
+}
+
+/// Returns true if `access` refers to an `input` signal.
+fn is_input(signal_types: &HashMap<VariableName, SignalType>, access: &VariableUse) -> bool {
+    matches!(signal_types.get(access.name()), Some(SignalType::Input))
+}
+
+/// Returns the signal type of `access` if it is a derived (output or
+/// intermediate) signal, and `None` otherwise (i.e. for inputs and
+/// non-signals).
+fn derived_signal_type(
+    signal_types: &HashMap<VariableName, SignalType>,
+    access: &VariableUse,
+) -> Option<SignalType> {
+    match signal_types.get(access.name()) {
+        Some(signal_type @ (SignalType::Output | SignalType::Intermediate)) => Some(*signal_type),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::parse_definition;
+    use program_structure::cfg::IntoCfg;
+    use program_structure::constants::Curve;
+
+    use super::*;
+
+    #[test]
+    fn test_sound_circuit_produces_no_reports() {
+        let src = r#"
+            template T() {
+                signal input in;
+                signal output out;
+                signal tmp;
+
+                tmp <== 2 * in;
+                out <== tmp + in;
+            }
+        "#;
+        validate_reports(src, 0);
+    }
+
+    #[test]
+    fn test_output_unconnected_to_input() {
+        // `out` is constrained, but only to itself and constants, never to `in`.
+        let src = r#"
+            template T() {
+                signal input in;
+                signal output out;
+
+                out === 2 * out + 1;
+            }
+        "#;
+        validate_reports(src, 1);
+    }
+
+    #[test]
+    fn test_witness_assignment_is_flagged() {
+        // `out` is assigned using `<--` but never constrained with `===`/`<==`.
+        let src = r#"
+            template T() {
+                signal input in;
+                signal output out;
+
+                out <-- in * in;
+            }
+        "#;
+        validate_reports(src, 1);
+    }
+
+    #[test]
+    fn test_array_elements_are_vetted_independently() {
+        // `out[0]` is tied to the input, but `out[1]` is constrained only to a
+        // constant. The dangling element must be flagged even though a sibling
+        // element of the same signal is sound.
+        let src = r#"
+            template T() {
+                signal input in;
+                signal output out[2];
+
+                out[0] <== in;
+                out[1] <== 5;
+            }
+        "#;
+        validate_reports(src, 1);
+    }
+
+    fn validate_reports(src: &str, expected: usize) {
+        let mut reports = ReportCollection::new();
+        let cfg = parse_definition(src)
+            .unwrap()
+            .into_cfg(&Curve::default(), &mut reports)
+            .unwrap()
+            .into_ssa()
+            .unwrap();
+        assert!(reports.is_empty());
+
+        let reports = run_under_constrained_signals(&cfg);
+        assert_eq!(reports.len(), expected);
+    }
+}
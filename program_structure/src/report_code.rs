@@ -0,0 +1,31 @@
+use std::fmt;
+
+/// A diagnostic identifier attached to every report produced by the parser, the
+/// compiler, and the analysis passes. The identifier is shown to the user and
+/// is used to refer to (and suppress) individual diagnostics.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ReportCode {
+    // Parser and compiler diagnostics.
+    ParseFail,
+    MultipleMainInComponent,
+    CompilerVersionError,
+    NoCompilerVersionWarning,
+    // Circuit analysis diagnostics.
+    UnderConstrainedSignal,
+    UnconstrainedSignal,
+}
+
+impl fmt::Display for ReportCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ReportCode::*;
+        let string = match self {
+            ParseFail => "P1000",
+            MultipleMainInComponent => "P1001",
+            CompilerVersionError => "P1002",
+            NoCompilerVersionWarning => "P1003",
+            UnderConstrainedSignal => "CA01",
+            UnconstrainedSignal => "CA02",
+        };
+        write!(f, "{string}")
+    }
+}